@@ -0,0 +1,191 @@
+//! Chip auto-detection by walking the ARM debug ROM table.
+
+use super::memory::romtable::RomTable;
+use super::memory::ADIMemoryInterface;
+use super::ArmCommunicationInterface;
+use crate::config::registry::ChipInfo;
+use crate::{Error, Memory};
+
+/// Manufacturer + part number read out of a SoC's top-level ROM table entry, used to look up
+/// the matching target in the chip registry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArmChipInfo {
+    pub manufacturer: jep106::JEP106Code,
+    pub part: u16,
+}
+
+impl ArmChipInfo {
+    /// Identify the connected chip by walking the ROM table reachable through `interface`.
+    ///
+    /// Returns `Ok(None)` when there is no ROM table at all (rather than an error), since that's
+    /// an expected outcome of "auto-detect found nothing to identify", not a bug.
+    pub fn read_from_rom_table(interface: &mut ArmCommunicationInterface) -> Result<Option<ArmChipInfo>, Error> {
+        let idcode = interface.read_dpidr()?;
+        log::debug!("DP IDCODE: {:#010x}", idcode);
+
+        let maps = interface.memory_access_ports()?;
+        let ap = match maps.first() {
+            Some(ap) => ap,
+            None => return Ok(None),
+        };
+
+        let mut memory = Memory::new(ADIMemoryInterface::<ArmCommunicationInterface>::new(
+            interface.clone(),
+            ap.id(),
+        ));
+
+        Self::from_rom_table_at(&mut memory, ap.base_address() as u64)
+    }
+
+    /// Resolve chip identification from the ROM table reachable at `base_address`.
+    ///
+    /// Split out from `read_from_rom_table` so the identification logic can be exercised
+    /// against a synthetic `Memory` in tests, without needing a real `ArmCommunicationInterface`.
+    fn from_rom_table_at(memory: &mut Memory, base_address: u64) -> Result<Option<ArmChipInfo>, Error> {
+        let rom_table = match RomTable::try_parse(memory, base_address) {
+            Ok(table) => table,
+            Err(_) => return Ok(None),
+        };
+
+        // The SoC-level identification is the ROM table's own peripheral ID - read at
+        // `base_address` itself - not any of the child components it points to. Child debug
+        // components (e.g. a generic Cortex-M SCS) are ARM IP and carry ARM's own JEP-106 code,
+        // not the silicon vendor's, so using one would misidentify almost every real chip, and
+        // would also depend on entry ordering the ROM table format doesn't actually guarantee.
+        let peripheral_id = &rom_table.peripheral_id;
+
+        Ok(Some(ArmChipInfo {
+            manufacturer: peripheral_id.jep106(),
+            part: peripheral_id.part_number(),
+        }))
+    }
+}
+
+impl From<ArmChipInfo> for ChipInfo {
+    fn from(chip: ArmChipInfo) -> ChipInfo {
+        ChipInfo::Arm(chip)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryInterface;
+
+    /// A synthetic in-memory stand-in for target RAM, driven through the same `MemoryInterface`
+    /// that real probes implement (mirroring the mock used for `Rtt::attach`'s tests).
+    struct MockMemory {
+        data: Vec<u8>,
+    }
+
+    impl MockMemory {
+        fn new(size: usize) -> Self {
+            MockMemory { data: vec![0; size] }
+        }
+
+        /// Write the four fixed CoreSight Component ID registers at `component_address + 0xFF0`.
+        fn write_component_id(&mut self, component_address: u32) {
+            let cidr = [0x0d, 0x10, 0x05, 0xb1];
+            let base = (component_address + 0xff0) as usize;
+            self.data[base..base + 4].copy_from_slice(&cidr);
+        }
+
+        /// Write the Peripheral ID registers (`PIDR0..4`) at `component_address + 0xFE0`/`0xFD0`
+        /// encoding `jep106` (continuation code + identity code) and a 12-bit `part` number, per
+        /// the ARM CoreSight Architecture Specification's fixed ID register layout.
+        fn write_peripheral_id(&mut self, component_address: u32, jep106: jep106::JEP106Code, part: u16) {
+            let pidr0 = (part & 0xff) as u8;
+            let pidr1 = (((jep106.id & 0x0f) << 4) | ((part >> 8) & 0x0f) as u8) as u8;
+            let pidr2 = 0x08 | ((jep106.id >> 4) & 0x07);
+            let pidr3 = 0u8;
+            let pidr4 = jep106.cc & 0x0f;
+
+            let pidr_base = (component_address + 0xfe0) as usize;
+            self.data[pidr_base] = pidr0;
+            self.data[pidr_base + 4] = pidr1;
+            self.data[pidr_base + 8] = pidr2;
+            self.data[pidr_base + 12] = pidr3;
+            self.data[(component_address + 0xfd0) as usize] = pidr4;
+        }
+
+        /// Lay out a ROM table at `base_address`: its own component ID registers, one entry
+        /// pointing at a child component `component_offset` bytes away, then the terminating
+        /// zero entry.
+        fn write_rom_table(&mut self, base_address: u32, table_id: jep106::JEP106Code, table_part: u16, component_offset: i32) {
+            self.write_component_id(base_address);
+            self.write_peripheral_id(base_address, table_id, table_part);
+
+            let entry = (component_offset & !0xfff) as u32 | 0b11; // present, 32-bit format
+            self.data[base_address as usize..base_address as usize + 4].copy_from_slice(&entry.to_le_bytes());
+            self.data[base_address as usize + 4..base_address as usize + 8].copy_from_slice(&0u32.to_le_bytes());
+        }
+    }
+
+    impl MemoryInterface for MockMemory {
+        fn read_word_32(&mut self, address: u32) -> Result<u32, Error> {
+            let mut buf = [0u8; 4];
+            self.read_block8(address, &mut buf)?;
+            Ok(u32::from_le_bytes(buf))
+        }
+
+        fn read_word_8(&mut self, address: u32) -> Result<u8, Error> {
+            Ok(self.data[address as usize])
+        }
+
+        fn read_block32(&mut self, address: u32, data: &mut [u32]) -> Result<(), Error> {
+            for (i, word) in data.iter_mut().enumerate() {
+                *word = self.read_word_32(address + (i as u32) * 4)?;
+            }
+            Ok(())
+        }
+
+        fn read_block8(&mut self, address: u32, data: &mut [u8]) -> Result<(), Error> {
+            let start = address as usize;
+            data.copy_from_slice(&self.data[start..start + data.len()]);
+            Ok(())
+        }
+
+        fn write_word_32(&mut self, address: u32, data: u32) -> Result<(), Error> {
+            self.write_block8(address, &data.to_le_bytes())
+        }
+
+        fn write_word_8(&mut self, address: u32, data: u8) -> Result<(), Error> {
+            self.data[address as usize] = data;
+            Ok(())
+        }
+
+        fn write_block32(&mut self, address: u32, data: &[u32]) -> Result<(), Error> {
+            for (i, word) in data.iter().enumerate() {
+                self.write_word_32(address + (i as u32) * 4, *word)?;
+            }
+            Ok(())
+        }
+
+        fn write_block8(&mut self, address: u32, data: &[u8]) -> Result<(), Error> {
+            let start = address as usize;
+            self.data[start..start + data.len()].copy_from_slice(data);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn resolves_to_the_rom_tables_own_id_not_its_first_child() {
+        let table_id = jep106::JEP106Code::new(0x04, 0x20);
+        let child_id = jep106::JEP106Code::new(0x02, 0x43);
+
+        let mut mock = MockMemory::new(0x3000);
+        mock.write_rom_table(0, table_id, 0x234, 0x1000);
+        mock.write_component_id(0x1000);
+        mock.write_peripheral_id(0x1000, child_id, 0x678);
+
+        let mut memory = Memory::new(mock);
+        let chip = ArmChipInfo::from_rom_table_at(&mut memory, 0)
+            .expect("rom table parse failed")
+            .expect("expected a resolved chip");
+
+        assert_eq!(chip.manufacturer, table_id);
+        assert_eq!(chip.part, 0x234);
+        assert_ne!(chip.manufacturer, child_id);
+        assert_ne!(chip.part, 0x678);
+    }
+}
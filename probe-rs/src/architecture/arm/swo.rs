@@ -0,0 +1,277 @@
+//! Decoder for the ITM/DWT trace protocol carried over SWO (`read_swv`'s raw byte stream).
+//!
+//! The wire format is a sequence of packets, each starting with a header byte that determines
+//! how many payload bytes follow and how to interpret them. Packets can straddle the chunk
+//! boundaries `read_swv` hands us, so [`Decoder`] buffers residual bytes between calls to
+//! [`Decoder::feed`].
+
+/// A single decoded ITM/DWT trace packet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TracePacket {
+    /// Data written by the target to an ITM stimulus port (e.g. `printf`-over-ITM).
+    Instrumentation { port: u8, data: Vec<u8> },
+    /// DWT event counter wrapping packet.
+    EventCounter { counters: u8 },
+    /// DWT exception entry/exit/return trace packet.
+    ExceptionTrace { exception_number: u16, function: u8 },
+    /// DWT periodic program counter sample.
+    PcSample { pc: u32 },
+    /// Local timestamp, as a delta encoded across the header and any continuation bytes.
+    LocalTimestamp { delta: u32 },
+    /// The trace sink couldn't keep up and packets were dropped.
+    Overflow,
+}
+
+/// A streaming ITM/DWT packet decoder fed by successive `read_swv` chunks.
+#[derive(Default)]
+pub struct Decoder {
+    buffer: Vec<u8>,
+}
+
+impl Decoder {
+    /// Create an empty decoder.
+    pub fn new() -> Decoder {
+        Decoder::default()
+    }
+
+    /// Feed a new chunk of raw SWO bytes and decode as many complete packets as possible.
+    ///
+    /// Any trailing bytes that don't yet form a complete packet are kept for the next call.
+    pub fn feed(&mut self, data: &[u8]) -> Vec<TracePacket> {
+        self.buffer.extend_from_slice(data);
+
+        let mut packets = Vec::new();
+        loop {
+            match decode_one(&self.buffer) {
+                Some((consumed, packet)) => {
+                    self.buffer.drain(..consumed);
+                    if let Some(packet) = packet {
+                        packets.push(packet);
+                    }
+                }
+                None => break,
+            }
+        }
+
+        packets
+    }
+}
+
+/// Try to decode a single packet from the front of `buf`.
+///
+/// Returns `Some((consumed, packet))` on success, where `packet` is `None` for a synchronization
+/// packet (which carries no data, just a resync point). Returns `None` if `buf` doesn't yet
+/// contain a complete packet.
+fn decode_one(buf: &[u8]) -> Option<(usize, Option<TracePacket>)> {
+    let header = *buf.first()?;
+
+    // Synchronization packet: a run of at least 47 zero bits terminated by a 1 bit. At byte
+    // granularity that's at least five 0x00 bytes followed by a byte whose only set bit is the
+    // final one (0x80).
+    if header == 0x00 {
+        let zeros = buf.iter().take_while(|&&b| b == 0x00).count();
+        if zeros + 1 > buf.len() {
+            return None; // might still be more zero bytes coming
+        }
+        if buf[zeros] == 0x80 && zeros >= 5 {
+            return Some((zeros + 1, None));
+        }
+        // Not actually a sync packet (too short, or not terminated by 0x80) - drop the first
+        // zero byte and let the caller resynchronize on the next header.
+        return Some((1, None));
+    }
+
+    match header & 0x03 {
+        0b01 | 0b10 | 0b11 => decode_source_packet(buf, header),
+        _ => decode_protocol_packet(buf, header),
+    }
+}
+
+fn decode_source_packet(buf: &[u8], header: u8) -> Option<(usize, Option<TracePacket>)> {
+    let size = match header & 0x03 {
+        0b01 => 1,
+        0b10 => 2,
+        0b11 => 4,
+        _ => unreachable!(),
+    };
+
+    if buf.len() < 1 + size {
+        return None;
+    }
+
+    let address = header >> 3;
+    let payload = &buf[1..1 + size];
+
+    let packet = if header & 0x04 == 0 {
+        TracePacket::Instrumentation {
+            port: address,
+            data: payload.to_vec(),
+        }
+    } else {
+        match address {
+            0 => TracePacket::EventCounter { counters: payload[0] },
+            1 => {
+                let value = u16::from_le_bytes([payload[0], payload.get(1).copied().unwrap_or(0)]);
+                // Per the ARMv7-M ARM's exception trace packet layout: byte 0 is exception
+                // number bits [7:0]; byte 1 bit 0 is exception number bit 8, and bits [2:1] are
+                // the function code - i.e. bits [10:9] of the combined 16-bit value, immediately
+                // following the 9-bit exception number with no gap.
+                TracePacket::ExceptionTrace {
+                    exception_number: value & 0x01ff,
+                    function: ((value >> 9) & 0x3) as u8,
+                }
+            }
+            2 => TracePacket::PcSample {
+                pc: u32::from_le_bytes(payload.try_into().unwrap_or([0; 4])),
+            },
+            _ => return Some((1 + size, None)),
+        }
+    };
+
+    Some((1 + size, Some(packet)))
+}
+
+fn decode_protocol_packet(buf: &[u8], header: u8) -> Option<(usize, Option<TracePacket>)> {
+    if header == 0x70 {
+        return Some((1, Some(TracePacket::Overflow)));
+    }
+
+    if header & 0x80 == 0 {
+        // Short-form local timestamp: the whole value lives in the header's upper nibble, and
+        // the packet is exactly one byte - there are no continuation bytes to read.
+        let delta = ((header >> 4) & 0x07) as u32;
+        return Some((1, Some(TracePacket::LocalTimestamp { delta })));
+    }
+
+    // Long-form local timestamp (header 0xC0): the value is carried entirely in the
+    // continuation bytes that follow, least-significant byte first, each with bit 7 set to
+    // continue and the terminal byte's bit 7 clear.
+    let mut delta = 0u32;
+    let mut consumed = 1;
+    let mut shift = 0;
+
+    loop {
+        match buf.get(consumed) {
+            Some(&byte) => {
+                delta |= ((byte & 0x7f) as u32) << shift;
+                consumed += 1;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+                shift += 7;
+            }
+            None => return None,
+        }
+    }
+
+    Some((consumed, Some(TracePacket::LocalTimestamp { delta })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_single_byte_instrumentation_packet() {
+        let mut decoder = Decoder::new();
+        // Header 0x01: source packet, size=1, port=0.
+        let packets = decoder.feed(&[0x01, b'X']);
+        assert_eq!(
+            packets,
+            vec![TracePacket::Instrumentation {
+                port: 0,
+                data: vec![b'X']
+            }]
+        );
+    }
+
+    #[test]
+    fn splits_packet_across_three_feeds() {
+        let mut decoder = Decoder::new();
+        // Header 0x03: source packet, size=4, port=0 - fed as three chunks of 1, 2 and 2 bytes.
+        assert_eq!(decoder.feed(&[0x03]), vec![]);
+        assert_eq!(decoder.feed(&[1, 2]), vec![]);
+        assert_eq!(
+            decoder.feed(&[3, 4]),
+            vec![TracePacket::Instrumentation {
+                port: 0,
+                data: vec![1, 2, 3, 4]
+            }]
+        );
+    }
+
+    #[test]
+    fn decodes_overflow_packet() {
+        let mut decoder = Decoder::new();
+        assert_eq!(decoder.feed(&[0x70]), vec![TracePacket::Overflow]);
+    }
+
+    #[test]
+    fn decodes_short_form_local_timestamp() {
+        let mut decoder = Decoder::new();
+        // Header 0x50: short-form local timestamp, delta = 0b101 = 5, one byte, no continuation.
+        assert_eq!(
+            decoder.feed(&[0x50]),
+            vec![TracePacket::LocalTimestamp { delta: 5 }]
+        );
+    }
+
+    #[test]
+    fn short_form_local_timestamp_does_not_eat_the_next_header() {
+        // A short-form timestamp packet must not consume the following instrumentation
+        // packet's header byte as a fake continuation byte.
+        let mut decoder = Decoder::new();
+        let packets = decoder.feed(&[0x50, 0x01, b'X']);
+        assert_eq!(
+            packets,
+            vec![
+                TracePacket::LocalTimestamp { delta: 5 },
+                TracePacket::Instrumentation {
+                    port: 0,
+                    data: vec![b'X']
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn decodes_long_form_local_timestamp() {
+        let mut decoder = Decoder::new();
+        // Header 0xC0: long-form local timestamp, value entirely in continuation bytes,
+        // little-endian 7-bit groups: 0x7f | (0x01 << 7) = 255.
+        let packets = decoder.feed(&[0xC0, 0xff, 0x01]);
+        assert_eq!(packets, vec![TracePacket::LocalTimestamp { delta: 255 }]);
+    }
+
+    #[test]
+    fn decodes_exception_trace_function_field() {
+        let mut decoder = Decoder::new();
+        // Header 0x0e: hardware source packet (bit 2 set), size=2, address=1 (exception trace).
+        // Payload: exception number 5 in bits [8:0], function 2 (exit) in bits [10:9].
+        let payload: u16 = 5 | (0b10 << 9);
+        let packets = decoder.feed(&[0x0e, payload as u8, (payload >> 8) as u8]);
+        assert_eq!(
+            packets,
+            vec![TracePacket::ExceptionTrace {
+                exception_number: 5,
+                function: 2,
+            }]
+        );
+    }
+
+    #[test]
+    fn max_exception_number_does_not_bleed_into_function_field() {
+        let mut decoder = Decoder::new();
+        // Exception number 0x1ff is the highest value the 9-bit field can hold; function must
+        // still decode as 0 rather than picking up the exception number's top bit.
+        let payload: u16 = 0x1ff;
+        let packets = decoder.feed(&[0x0e, payload as u8, (payload >> 8) as u8]);
+        assert_eq!(
+            packets,
+            vec![TracePacket::ExceptionTrace {
+                exception_number: 0x1ff,
+                function: 0,
+            }]
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! Debug Transport Module: drives the RISC-V DMI (Debug Module Interface) over the probe's
+//! JTAG transport, and issues Abstract Commands against the hart's debug module (per the
+//! RISC-V Debug Specification's Program Buffer-free "abstract command" access path).
+
+use crate::{Error, Probe};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// DMI register addresses used by the abstract-command memory/register access path.
+mod dm_register {
+    pub const DATA0: u8 = 0x04;
+    pub const ABSTRACTCS: u8 = 0x16;
+    pub const COMMAND: u8 = 0x17;
+    pub const SBCS: u8 = 0x38;
+    pub const SBADDRESS0: u8 = 0x39;
+    pub const SBDATA0: u8 = 0x3c;
+}
+
+/// `cmdtype` field for an Access Register abstract command.
+const CMD_ACCESS_REGISTER: u32 = 0;
+/// `transfer` bit: actually move data between `data0` and the target register.
+const CMD_TRANSFER: u32 = 1 << 17;
+/// `write` bit: register access is a write rather than a read.
+const CMD_WRITE: u32 = 1 << 16;
+/// `aarsize` = 2 (32-bit access).
+const CMD_AARSIZE_32: u32 = 2 << 20;
+
+/// `busy` bit in `abstractcs`, set while a command is still executing.
+const ABSTRACTCS_BUSY: u32 = 1 << 12;
+/// `cmderr` field in `abstractcs`, bits [10:8]. Sticky until written back with a 1, per the
+/// Debug Spec - it stays set (masking any later command's result) until explicitly cleared.
+const ABSTRACTCS_CMDERR_MASK: u32 = 0x7 << 8;
+
+/// Upper bound on `wait_for_command`'s polling loop, so a target that never clears `busy`
+/// (dead hart, broken DM) fails fast instead of hanging the caller forever.
+const ABSTRACTCS_POLL_LIMIT: u32 = 100_000;
+
+/// `sbbusy` bit in `sbcs`, set while a system bus access is still in flight.
+const SBCS_SBBUSY: u32 = 1 << 21;
+/// `sberror` field in `sbcs`, bits [14:12]. Sticky until written back with a 1, same as
+/// `abstractcs.cmderr`.
+const SBCS_SBERROR_MASK: u32 = 0x7 << 12;
+
+/// Upper bound on `wait_for_sbus`'s polling loop, mirroring `ABSTRACTCS_POLL_LIMIT`.
+const SBCS_POLL_LIMIT: u32 = 100_000;
+
+/// Handle to a RISC-V target's Debug Module, reached through the probe's JTAG transport.
+///
+/// Cheaply `Clone`, mirroring `ArmCommunicationInterface`: every clone shares the same
+/// underlying probe handle.
+#[derive(Clone)]
+pub(crate) struct Dtm {
+    probe: Rc<RefCell<Probe>>,
+}
+
+impl Dtm {
+    pub fn new(probe: Probe) -> Result<Dtm, Error> {
+        Ok(Dtm {
+            probe: Rc::new(RefCell::new(probe)),
+        })
+    }
+
+    /// Read a DMI register.
+    fn dmi_read(&self, address: u8) -> Result<u32, Error> {
+        self.probe.borrow_mut().jtag_dmi_read(address)
+    }
+
+    /// Write a DMI register.
+    fn dmi_write(&self, address: u8, value: u32) -> Result<(), Error> {
+        self.probe.borrow_mut().jtag_dmi_write(address, value)
+    }
+
+    /// Block until the current abstract command completes, per the `abstractcs.busy` bit, then
+    /// check `cmderr` for a command failure.
+    ///
+    /// Bounded by `ABSTRACTCS_POLL_LIMIT` so a hart that never clears `busy` surfaces as an
+    /// error instead of hanging the caller; `cmderr` is not read or cleared in that case. A
+    /// nonzero `cmderr` is itself an error, and is cleared (by writing 1s back) before
+    /// returning, so it can't mask the next command.
+    fn wait_for_command(&self) -> Result<(), Error> {
+        for _ in 0..ABSTRACTCS_POLL_LIMIT {
+            let abstractcs = self.dmi_read(dm_register::ABSTRACTCS)?;
+            if abstractcs & ABSTRACTCS_BUSY != 0 {
+                continue;
+            }
+
+            let cmderr = (abstractcs & ABSTRACTCS_CMDERR_MASK) >> 8;
+            if cmderr == 0 {
+                return Ok(());
+            }
+
+            self.dmi_write(dm_register::ABSTRACTCS, ABSTRACTCS_CMDERR_MASK)?;
+            return Err(Error::architecture_specific(AbstractCommandError::from_cmderr(cmderr)));
+        }
+
+        Err(Error::architecture_specific(DtmError::AbstractCommandTimeout))
+    }
+
+    /// Read a 32-bit GPR/CSR through the Access Register abstract command. `regno` follows the
+    /// Debug Spec's register numbering (e.g. `0x1000 + n` for GPR `xn`).
+    pub fn read_register(&self, regno: u16) -> Result<u32, Error> {
+        let command = CMD_ACCESS_REGISTER | CMD_AARSIZE_32 | CMD_TRANSFER | regno as u32;
+        self.dmi_write(dm_register::COMMAND, command)?;
+        self.wait_for_command()?;
+        self.dmi_read(dm_register::DATA0)
+    }
+
+    /// Write a 32-bit GPR/CSR through the Access Register abstract command.
+    pub fn write_register(&self, regno: u16, value: u32) -> Result<(), Error> {
+        self.dmi_write(dm_register::DATA0, value)?;
+        let command = CMD_ACCESS_REGISTER | CMD_AARSIZE_32 | CMD_TRANSFER | CMD_WRITE | regno as u32;
+        self.dmi_write(dm_register::COMMAND, command)?;
+        self.wait_for_command()
+    }
+
+    /// Block until the in-flight system bus access completes, per `sbcs.sbbusy`, then check
+    /// `sbcs.sberror`.
+    ///
+    /// System bus accesses aren't guaranteed to complete synchronously with the DMI write that
+    /// triggers them, so callers must wait here before trusting `sbdata0` - otherwise a slow bus
+    /// can hand back a stale or garbage word, or a bus error can pass by unnoticed.
+    fn wait_for_sbus(&self) -> Result<(), Error> {
+        for _ in 0..SBCS_POLL_LIMIT {
+            let sbcs = self.dmi_read(dm_register::SBCS)?;
+            if sbcs & SBCS_SBBUSY != 0 {
+                continue;
+            }
+
+            let sberror = (sbcs & SBCS_SBERROR_MASK) >> 12;
+            if sberror == 0 {
+                return Ok(());
+            }
+
+            self.dmi_write(dm_register::SBCS, SBCS_SBERROR_MASK)?;
+            return Err(Error::architecture_specific(SystemBusError::from_sberror(sberror)));
+        }
+
+        Err(Error::architecture_specific(DtmError::SystemBusTimeout))
+    }
+
+    /// Read one 32-bit word of target memory through the System Bus Access registers.
+    pub fn read_memory_word(&self, address: u32) -> Result<u32, Error> {
+        self.dmi_write(dm_register::SBCS, 2 << 17 /* sbaccess = 32 bits */)?;
+        self.dmi_write(dm_register::SBADDRESS0, address)?;
+        self.wait_for_sbus()?;
+        self.dmi_read(dm_register::SBDATA0)
+    }
+
+    /// Write one 32-bit word of target memory through the System Bus Access registers.
+    pub fn write_memory_word(&self, address: u32, value: u32) -> Result<(), Error> {
+        self.dmi_write(dm_register::SBCS, 2 << 17 /* sbaccess = 32 bits */)?;
+        self.dmi_write(dm_register::SBADDRESS0, address)?;
+        self.dmi_write(dm_register::SBDATA0, value)?;
+        self.wait_for_sbus()
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+enum DtmError {
+    #[error("abstract command did not complete within {} DMI polls", ABSTRACTCS_POLL_LIMIT)]
+    AbstractCommandTimeout,
+    #[error("system bus access did not complete within {} DMI polls", SBCS_POLL_LIMIT)]
+    SystemBusTimeout,
+}
+
+/// Decoded `abstractcs.cmderr`, per the Debug Spec's abstract command error codes.
+#[derive(Debug, thiserror::Error)]
+enum AbstractCommandError {
+    #[error("abstract command attempted while a previous command was still busy")]
+    Busy,
+    #[error("target does not support this abstract command")]
+    NotSupported,
+    #[error("exception occurred while executing the abstract command")]
+    Exception,
+    #[error("cannot execute the abstract command while the hart is in the wrong halt/resume state")]
+    HaltResume,
+    #[error("bus error while executing the abstract command")]
+    Bus,
+    #[error("abstract command failed with reserved cmderr code {0:#x}")]
+    Other(u32),
+}
+
+impl AbstractCommandError {
+    fn from_cmderr(cmderr: u32) -> AbstractCommandError {
+        match cmderr {
+            1 => AbstractCommandError::Busy,
+            2 => AbstractCommandError::NotSupported,
+            3 => AbstractCommandError::Exception,
+            4 => AbstractCommandError::HaltResume,
+            5 => AbstractCommandError::Bus,
+            other => AbstractCommandError::Other(other),
+        }
+    }
+}
+
+/// Decoded `sbcs.sberror`, per the Debug Spec's system bus access error codes.
+#[derive(Debug, thiserror::Error)]
+enum SystemBusError {
+    #[error("system bus access to a misaligned or unsupported address")]
+    BadAddress,
+    #[error("system bus access timed out")]
+    Timeout,
+    #[error("system bus access attempted while a previous access was still busy")]
+    Busy,
+    #[error("system bus access failed with reserved sberror code {0:#x}")]
+    Other(u32),
+}
+
+impl SystemBusError {
+    fn from_sberror(sberror: u32) -> SystemBusError {
+        match sberror {
+            1 => SystemBusError::Timeout,
+            2 => SystemBusError::BadAddress,
+            4 => SystemBusError::Busy,
+            other => SystemBusError::Other(other),
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! `Memory` backing for RISC-V, built on the Debug Module's System Bus Access abstract command
+//! rather than an ARM-style memory-mapped AP.
+
+use super::dtm::Dtm;
+use crate::memory::MemoryInterface;
+use crate::Error;
+
+/// Memory access through the RISC-V abstract-command / system-bus-access interface.
+#[derive(Clone)]
+pub struct RiscvMemoryInterface {
+    dtm: Dtm,
+}
+
+impl RiscvMemoryInterface {
+    pub(crate) fn new(dtm: Dtm) -> RiscvMemoryInterface {
+        RiscvMemoryInterface { dtm }
+    }
+}
+
+impl MemoryInterface for RiscvMemoryInterface {
+    fn read_word_32(&mut self, address: u32) -> Result<u32, Error> {
+        self.dtm.read_memory_word(address)
+    }
+
+    fn read_word_8(&mut self, address: u32) -> Result<u8, Error> {
+        let word = self.dtm.read_memory_word(address & !0x3)?;
+        let shift = (address & 0x3) * 8;
+        Ok(((word >> shift) & 0xff) as u8)
+    }
+
+    fn read_block32(&mut self, address: u32, data: &mut [u32]) -> Result<(), Error> {
+        for (i, word) in data.iter_mut().enumerate() {
+            *word = self.dtm.read_memory_word(address + (i as u32) * 4)?;
+        }
+        Ok(())
+    }
+
+    fn read_block8(&mut self, address: u32, data: &mut [u8]) -> Result<(), Error> {
+        for (i, byte) in data.iter_mut().enumerate() {
+            *byte = self.read_word_8(address + i as u32)?;
+        }
+        Ok(())
+    }
+
+    fn write_word_32(&mut self, address: u32, data: u32) -> Result<(), Error> {
+        self.dtm.write_memory_word(address, data)
+    }
+
+    fn write_word_8(&mut self, address: u32, data: u8) -> Result<(), Error> {
+        let aligned = address & !0x3;
+        let shift = (address & 0x3) * 8;
+        let word = self.dtm.read_memory_word(aligned)?;
+        let word = (word & !(0xffu32 << shift)) | ((data as u32) << shift);
+        self.dtm.write_memory_word(aligned, word)
+    }
+
+    fn write_block32(&mut self, address: u32, data: &[u32]) -> Result<(), Error> {
+        for (i, word) in data.iter().enumerate() {
+            self.dtm.write_memory_word(address + (i as u32) * 4, *word)?;
+        }
+        Ok(())
+    }
+
+    fn write_block8(&mut self, address: u32, data: &[u8]) -> Result<(), Error> {
+        for (i, byte) in data.iter().enumerate() {
+            self.write_word_8(address + i as u32, *byte)?;
+        }
+        Ok(())
+    }
+}
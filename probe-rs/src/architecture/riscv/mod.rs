@@ -0,0 +1,44 @@
+//! RISC-V debug support.
+//!
+//! Unlike ARM's ADI, which addresses memory through a memory-mapped Access Port, RISC-V's
+//! debug module exposes a small DMI (Debug Module Interface) register set reached over JTAG,
+//! and reads/writes registers and memory by driving Abstract Commands against it.
+
+mod dtm;
+mod memory;
+
+pub use memory::RiscvMemoryInterface;
+
+use self::dtm::Dtm;
+use crate::{Error, Memory, Probe};
+
+/// Handle to a RISC-V target's Debug Module.
+#[derive(Clone)]
+pub struct RiscvCommunicationInterface {
+    dtm: Dtm,
+}
+
+impl RiscvCommunicationInterface {
+    /// Open the Debug Transport Module over `probe`'s JTAG interface.
+    pub fn new(probe: Probe) -> Result<RiscvCommunicationInterface, Error> {
+        Ok(RiscvCommunicationInterface { dtm: Dtm::new(probe)? })
+    }
+
+    /// A `Memory` backed by the abstract-command / system-bus-access interface.
+    ///
+    /// RISC-V has no ARM-style dedicated-memory-AP fast path, so unlike
+    /// `ArmCommunicationInterface` this always goes through the Debug Module.
+    pub fn memory_interface(&self) -> Memory {
+        Memory::new(RiscvMemoryInterface::new(self.dtm.clone()))
+    }
+}
+
+/// Build the "not supported yet" error for operations that only exist on ARM today, such as
+/// SWV/SWO tracing.
+pub fn unsupported(what: &str) -> Error {
+    Error::architecture_specific(UnsupportedError(what.to_string()))
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("{0} is not supported on RISC-V targets yet")]
+struct UnsupportedError(String);
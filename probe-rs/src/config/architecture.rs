@@ -0,0 +1,7 @@
+/// Debug architecture of a core, used to pick the communication interface `Session` talks to
+/// it through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Architecture {
+    Arm,
+    Riscv,
+}
@@ -0,0 +1,15 @@
+use crate::core::CoreType;
+
+/// How to reach one of a target's cores: its core type, and which memory Access Port its memory
+/// is reached through on ARM. RISC-V sessions do not use `ap`: all cores share one Debug Module
+/// memory view.
+///
+/// Single-core targets still get a one-element `Target::cores`, so `Session` never has to
+/// special-case "no per-core config" versus "one core at AP 0".
+#[derive(Debug, Clone)]
+pub struct CoreAccessOptions {
+    pub core_type: CoreType,
+    /// Index into `ArmCommunicationInterface::memory_access_ports()` that this core's memory is
+    /// reached through.
+    pub ap: usize,
+}
@@ -0,0 +1,36 @@
+use super::{Architecture, CoreAccessOptions, MemoryRegion, RawFlashAlgorithm};
+use crate::core::CoreType;
+
+/// Everything probe-rs knows about one chip: its cores and how to reach their memory, its flash
+/// algorithms, and its memory map - either looked up from the registry by name or chip info, or
+/// supplied directly via `TargetSelector::Specified`.
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub flash_algorithms: Vec<RawFlashAlgorithm>,
+    pub memory_map: Vec<MemoryRegion>,
+    /// Which debug architecture this target's cores use - selects the `ArchitectureSession`
+    /// variant `Session::new` builds.
+    pub architecture: Architecture,
+    /// Every core on the target, in core-index order. On ARM, `Session::attach_to_memory(id)`
+    /// reaches core `id`'s memory through `cores[id].ap`.
+    pub cores: Vec<CoreAccessOptions>,
+}
+
+impl Target {
+    /// Build a `Target` for a chip description that only describes a single core - the shape
+    /// every chip description had before multi-core support, and still the common case: one
+    /// `CoreAccessOptions` at AP 0.
+    pub fn single_core(
+        core_type: CoreType,
+        architecture: Architecture,
+        flash_algorithms: Vec<RawFlashAlgorithm>,
+        memory_map: Vec<MemoryRegion>,
+    ) -> Target {
+        Target {
+            architecture,
+            cores: vec![CoreAccessOptions { core_type, ap: 0 }],
+            flash_algorithms,
+            memory_map,
+        }
+    }
+}
@@ -0,0 +1,6 @@
+//! Debug-information facilities that sit on top of the core/memory access primitives: currently
+//! just stack unwinding, but the natural home for source-level debug support in general.
+
+mod unwind;
+
+pub use unwind::{unwind, ElfFile, Frame};
@@ -0,0 +1,344 @@
+//! Stack backtrace unwinding for a halted core, using the `.debug_frame` CFI encoded in the
+//! target's ELF file.
+//!
+//! This walks frames by asking `gimli` for the unwind rules that apply at the current PC,
+//! using them to recover the previous frame's CFA (Canonical Frame Address) and callee-saved
+//! registers, then repeating from the recovered return address.
+
+use gimli::{
+    BaseAddresses, CfaRule, DebugFrame, EndianSlice, LittleEndian, RegisterRule,
+    UninitializedUnwindContext, UnwindSection, UnwindTableRow,
+};
+
+use crate::{Core, Error};
+
+/// DWARF register number of the ARM program counter (r15).
+const REG_PC: u16 = 15;
+/// DWARF register number of the ARM stack pointer (r13).
+const REG_SP: u16 = 13;
+/// DWARF register number of the ARM link register (r14), which also serves as the return
+/// address register in `.debug_frame` CFI for leaf-less frames.
+const REG_LR: u16 = 14;
+/// ARM has 16 core registers (r0-r15); that's also the DWARF register space we unwind over.
+const NUM_REGISTERS: usize = 16;
+
+/// A parsed ELF image, kept around only for the sections/symbols the debugger needs.
+pub struct ElfFile {
+    debug_frame: Vec<u8>,
+    symbols: Vec<(u32, u32, String)>,
+}
+
+impl ElfFile {
+    /// Parse `data` as an ELF file, extracting `.debug_frame` and the symbol table.
+    pub fn from_bytes(data: &[u8]) -> Result<ElfFile, Error> {
+        let elf = goblin::elf::Elf::parse(data).map_err(Error::architecture_specific)?;
+
+        let debug_frame = elf
+            .section_headers
+            .iter()
+            .find(|sh| elf.shdr_strtab.get_at(sh.sh_name) == Some(".debug_frame"))
+            .map(|sh| {
+                let start = sh.sh_offset as usize;
+                let end = start + sh.sh_size as usize;
+                data.get(start..end)
+                    .map(<[u8]>::to_vec)
+                    .ok_or(TruncatedSectionError(".debug_frame"))
+            })
+            .transpose()
+            .map_err(Error::architecture_specific)?
+            .unwrap_or_default();
+
+        let mut symbols = Vec::new();
+        for sym in elf.syms.iter() {
+            if sym.st_size == 0 || sym.st_name == 0 {
+                continue;
+            }
+            if let Some(name) = elf.strtab.get_at(sym.st_name) {
+                symbols.push((sym.st_value as u32, sym.st_size as u32, name.to_owned()));
+            }
+        }
+
+        Ok(ElfFile { debug_frame, symbols })
+    }
+
+    /// Find the function symbol containing `address`, if any.
+    fn function_at(&self, address: u32) -> Option<&str> {
+        self.symbols
+            .iter()
+            .find(|(start, size, _)| address >= *start && address < start + size)
+            .map(|(_, _, name)| name.as_str())
+    }
+}
+
+/// A section header claimed a byte range that runs past the end of the file - the ELF is
+/// truncated or corrupt.
+#[derive(Debug, thiserror::Error)]
+#[error("ELF section `{0}` extends past the end of the file")]
+struct TruncatedSectionError(&'static str);
+
+/// One frame of a recovered call stack.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// The return address (for frame 0, the current PC), with the Thumb bit already masked off.
+    pub pc: u32,
+    /// Name of the function containing `pc`, resolved via the ELF symbol table.
+    pub function_name: Option<String>,
+}
+
+/// What `unwind` needs from a halted target: its core registers, and the memory callee-saved
+/// registers are spilled to. Split out from `Core` so the unwind loop itself can be exercised
+/// against a fixed register/memory fixture in tests.
+trait UnwindTarget {
+    fn read_core_reg(&mut self, reg: u16) -> Result<u32, Error>;
+    fn read_word_32(&mut self, addr: u32) -> Result<u32, Error>;
+}
+
+impl UnwindTarget for Core {
+    fn read_core_reg(&mut self, reg: u16) -> Result<u32, Error> {
+        Core::read_core_reg(self, reg)
+    }
+
+    fn read_word_32(&mut self, addr: u32) -> Result<u32, Error> {
+        Core::read_word_32(self, addr)
+    }
+}
+
+/// Unwind the call stack of a halted core, using `.debug_frame` CFI from `elf`.
+pub fn unwind(core: &mut Core, elf: &ElfFile) -> Result<Vec<Frame>, Error> {
+    unwind_target(core, elf)
+}
+
+fn unwind_target(target: &mut impl UnwindTarget, elf: &ElfFile) -> Result<Vec<Frame>, Error> {
+    let debug_frame = DebugFrame::new(&elf.debug_frame, LittleEndian);
+    let bases = BaseAddresses::default();
+    let mut ctx = UninitializedUnwindContext::new();
+
+    let mut registers = [0u32; NUM_REGISTERS];
+    for (i, reg) in registers.iter_mut().enumerate() {
+        *reg = target.read_core_reg(i as u16)?;
+    }
+
+    let mut frames = Vec::new();
+    let mut previous_cfa = None;
+
+    loop {
+        let pc = registers[REG_PC as usize] & !1;
+        if pc == 0 {
+            break;
+        }
+
+        frames.push(Frame {
+            pc,
+            function_name: elf.function_at(pc).map(str::to_owned),
+        });
+
+        if elf.function_at(pc) == Some("main") {
+            break;
+        }
+
+        let unwind_info = match debug_frame.unwind_info_for_address(
+            &bases,
+            &mut ctx,
+            pc as u64,
+            DebugFrame::cie_from_offset,
+        ) {
+            Ok(info) => info,
+            Err(_) => break,
+        };
+
+        let cfa = match compute_cfa(unwind_info, &registers) {
+            Some(cfa) => cfa,
+            None => break,
+        };
+
+        // A CFA that doesn't strictly increase means we've looped - bail out rather than spin.
+        if let Some(previous) = previous_cfa {
+            if cfa <= previous {
+                break;
+            }
+        }
+        previous_cfa = Some(cfa);
+
+        let mut next_registers = registers;
+        for reg in 0..NUM_REGISTERS as u16 {
+            match unwind_info.register(gimli::Register(reg)) {
+                RegisterRule::Undefined | RegisterRule::SameValue => {}
+                RegisterRule::Offset(offset) => {
+                    let addr = (cfa as i64 + offset) as u32;
+                    next_registers[reg as usize] = target.read_word_32(addr)?;
+                }
+                _ => {}
+            }
+        }
+        next_registers[REG_SP as usize] = cfa as u32;
+
+        let return_address = next_registers[REG_LR as usize];
+        if return_address == 0 {
+            break;
+        }
+        next_registers[REG_PC as usize] = return_address;
+
+        registers = next_registers;
+    }
+
+    Ok(frames)
+}
+
+fn compute_cfa(row: &UnwindTableRow<EndianSlice<LittleEndian>>, registers: &[u32; NUM_REGISTERS]) -> Option<u64> {
+    match row.cfa() {
+        CfaRule::RegisterAndOffset { register, offset } => {
+            Some((registers[register.0 as usize] as i64 + offset) as u64)
+        }
+        CfaRule::Expression(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn write_uleb128(buf: &mut Vec<u8>, mut value: u64) {
+        loop {
+            let mut byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value != 0 {
+                byte |= 0x80;
+            }
+            buf.push(byte);
+            if value == 0 {
+                break;
+            }
+        }
+    }
+
+    fn write_sleb128(buf: &mut Vec<u8>, mut value: i64) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            let sign_bit_set = byte & 0x40 != 0;
+            if (value == 0 && !sign_bit_set) || (value == -1 && sign_bit_set) {
+                buf.push(byte);
+                break;
+            }
+            buf.push(byte | 0x80);
+        }
+    }
+
+    fn pad_to_word(buf: &mut Vec<u8>) {
+        while buf.len() % 4 != 0 {
+            buf.push(0x00); // DW_CFA_nop
+        }
+    }
+
+    /// Build a minimal one-CIE `.debug_frame` section whose CIE defines `CFA = r13 + cfa_offset`
+    /// and whose single FDE (covering `[initial_location, initial_location + address_range)`)
+    /// says the return address register (`r14`) is saved at `CFA - 4`.
+    fn synthetic_debug_frame(cfa_offset: i64, initial_location: u32, address_range: u32) -> Vec<u8> {
+        let mut cie_body = Vec::new();
+        cie_body.push(4u8); // version
+        cie_body.push(0u8); // augmentation string: empty
+        cie_body.push(4u8); // address_size
+        cie_body.push(0u8); // segment_selector_size
+        write_uleb128(&mut cie_body, 1); // code_alignment_factor
+        write_sleb128(&mut cie_body, -4); // data_alignment_factor
+        write_uleb128(&mut cie_body, REG_LR as u64); // return_address_register
+        cie_body.push(0x0c); // DW_CFA_def_cfa
+        write_uleb128(&mut cie_body, REG_SP as u64);
+        write_uleb128(&mut cie_body, cfa_offset as u64);
+        pad_to_word(&mut cie_body);
+
+        let mut section = Vec::new();
+        section.extend_from_slice(&((cie_body.len() + 4) as u32).to_le_bytes());
+        section.extend_from_slice(&0xffff_ffffu32.to_le_bytes()); // CIE_id
+        section.extend_from_slice(&cie_body);
+
+        let mut fde_body = Vec::new();
+        fde_body.extend_from_slice(&0u32.to_le_bytes()); // CIE_pointer: our CIE starts at offset 0
+        fde_body.extend_from_slice(&initial_location.to_le_bytes());
+        fde_body.extend_from_slice(&address_range.to_le_bytes());
+        fde_body.push(0x80 | REG_LR as u8); // DW_CFA_offset(r14, 1)
+        write_uleb128(&mut fde_body, 1);
+        pad_to_word(&mut fde_body);
+
+        section.extend_from_slice(&(fde_body.len() as u32).to_le_bytes());
+        section.extend_from_slice(&fde_body);
+        section
+    }
+
+    struct FixedTarget {
+        registers: [u32; NUM_REGISTERS],
+        memory: HashMap<u32, u32>,
+    }
+
+    impl FixedTarget {
+        fn new() -> FixedTarget {
+            FixedTarget {
+                registers: [0; NUM_REGISTERS],
+                memory: HashMap::new(),
+            }
+        }
+    }
+
+    impl UnwindTarget for FixedTarget {
+        fn read_core_reg(&mut self, reg: u16) -> Result<u32, Error> {
+            Ok(self.registers[reg as usize])
+        }
+
+        fn read_word_32(&mut self, addr: u32) -> Result<u32, Error> {
+            Ok(*self.memory.get(&addr).unwrap_or(&0))
+        }
+    }
+
+    fn elf_with_symbols(debug_frame: Vec<u8>, symbols: Vec<(u32, u32, &str)>) -> ElfFile {
+        ElfFile {
+            debug_frame,
+            symbols: symbols.into_iter().map(|(a, s, n)| (a, s, n.to_owned())).collect(),
+        }
+    }
+
+    #[test]
+    fn unwinds_two_frames_and_stops_at_main() {
+        let elf = elf_with_symbols(
+            synthetic_debug_frame(8, 0x1000, 0x100),
+            vec![(0x1000, 0x100, "foo"), (0x2000, 0x100, "main")],
+        );
+
+        let mut target = FixedTarget::new();
+        target.registers[REG_PC as usize] = 0x1001; // Thumb bit set
+        target.registers[REG_SP as usize] = 0x3000;
+        // CFA = SP(0x3000) + 8 = 0x3008; LR is saved at CFA - 4 = 0x3004, holding the return
+        // address into `main` (Thumb bit set).
+        target.memory.insert(0x3004, 0x2001);
+
+        let frames = unwind_target(&mut target, &elf).unwrap();
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].pc, 0x1000);
+        assert_eq!(frames[0].function_name.as_deref(), Some("foo"));
+        assert_eq!(frames[1].pc, 0x2000);
+        assert_eq!(frames[1].function_name.as_deref(), Some("main"));
+    }
+
+    #[test]
+    fn stops_on_non_increasing_cfa_instead_of_looping_forever() {
+        // `cfa_offset = 0` makes every frame's CFA equal to the (unmoving) stack pointer, and
+        // the saved return address always points back into the same function - as if the stack
+        // were corrupted into a cycle. The loop guard must stop this in a bounded number of
+        // iterations rather than spinning.
+        let elf = elf_with_symbols(synthetic_debug_frame(0, 0x1000, 0x100), vec![(0x1000, 0x100, "foo")]);
+
+        let mut target = FixedTarget::new();
+        target.registers[REG_PC as usize] = 0x1001;
+        target.registers[REG_SP as usize] = 0x3000;
+        // CFA = SP(0x3000) + 0 = 0x3000; LR saved at CFA - 4 = 0x2ffc, pointing right back at
+        // `foo`'s own start.
+        target.memory.insert(0x2ffc, 0x1001);
+
+        let frames = unwind_target(&mut target, &elf).unwrap();
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].pc, 0x1000);
+        assert_eq!(frames[1].pc, 0x1000);
+    }
+}
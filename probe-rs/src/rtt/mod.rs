@@ -0,0 +1,446 @@
+//! Minimal implementation of the SEGGER RTT (Real-Time Transfer) protocol.
+//!
+//! RTT works by having the target firmware place a control block somewhere in RAM. The
+//! control block describes a set of ring buffers ("channels"): "up" channels carry data from
+//! the target to the host, "down" channels carry data from the host to the target. We never
+//! touch the target's view of the buffers directly; we only read/write the control block and
+//! buffer memory through the debug probe.
+
+use crate::config::MemoryRegion;
+use crate::{Error, Memory};
+
+/// The control block always starts with this magic string, written by the target as the very
+/// last step of RTT initialization. Until it appears, the control block should be treated as
+/// not-yet-initialized rather than an error.
+const RTT_ID: &[u8; 16] = b"SEGGER RTT\0\0\0\0\0\0";
+
+/// Where to look for the RTT control block in target memory.
+#[derive(Debug, Clone)]
+pub enum ScanRegion {
+    /// Scan all RAM regions in the target's memory map.
+    Ram,
+    /// The control block is known to start at exactly this address.
+    Exact(u32),
+    /// Scan only this address range.
+    Range(core::ops::Range<u32>),
+}
+
+/// A handle to a target's RTT control block and its channels.
+pub struct Rtt {
+    memory: Memory,
+    ptr: u32,
+    up_channels: Vec<UpChannel>,
+    down_channels: Vec<DownChannel>,
+}
+
+impl Rtt {
+    /// Attach to the RTT control block, scanning `scan` for the `SEGGER RTT` magic.
+    pub(crate) fn attach(mut memory: Memory, scan: ScanRegion, memory_map: &[MemoryRegion]) -> Result<Rtt, Error> {
+        let ptr = find_control_block(&mut memory, &scan, memory_map)?;
+
+        let mut max_up = [0u8; 4];
+        let mut max_down = [0u8; 4];
+        memory.read_block8(ptr + 16, &mut max_up)?;
+        memory.read_block8(ptr + 20, &mut max_down)?;
+        let max_up_channels = u32::from_le_bytes(max_up) as usize;
+        let max_down_channels = u32::from_le_bytes(max_down) as usize;
+
+        // Buffer descriptors immediately follow the header: up channels, then down channels.
+        let descriptors_base = ptr + 24;
+
+        let mut up_channels = Vec::with_capacity(max_up_channels);
+        for i in 0..max_up_channels {
+            let addr = descriptors_base + (i as u32) * CHANNEL_DESCRIPTOR_SIZE;
+            up_channels.push(UpChannel(Channel::read(&mut memory, addr)?));
+        }
+
+        let mut down_channels = Vec::with_capacity(max_down_channels);
+        for i in 0..max_down_channels {
+            let addr = descriptors_base + ((max_up_channels + i) as u32) * CHANNEL_DESCRIPTOR_SIZE;
+            down_channels.push(DownChannel(Channel::read(&mut memory, addr)?));
+        }
+
+        Ok(Rtt {
+            memory,
+            ptr,
+            up_channels,
+            down_channels,
+        })
+    }
+
+    /// Address of the control block in target memory.
+    pub fn ptr(&self) -> u32 {
+        self.ptr
+    }
+
+    /// The up (target to host) channels, in the order the target declared them.
+    pub fn up_channels(&mut self) -> &mut [UpChannel] {
+        &mut self.up_channels
+    }
+
+    /// The down (host to target) channels, in the order the target declared them.
+    pub fn down_channels(&mut self) -> &mut [DownChannel] {
+        &mut self.down_channels
+    }
+
+    pub(crate) fn memory(&mut self) -> &mut Memory {
+        &mut self.memory
+    }
+}
+
+/// Size in bytes of one `{ sName, pBuffer, SizeOfBuffer, WrOff, RdOff, Flags }` descriptor.
+const CHANNEL_DESCRIPTOR_SIZE: u32 = 24;
+
+struct Channel {
+    name_ptr: u32,
+    buffer_ptr: u32,
+    size: u32,
+    write_offset_addr: u32,
+    read_offset_addr: u32,
+}
+
+impl Channel {
+    fn read(memory: &mut Memory, addr: u32) -> Result<Channel, Error> {
+        let name_ptr = memory.read_word_32(addr)?;
+        let buffer_ptr = memory.read_word_32(addr + 4)?;
+        let size = memory.read_word_32(addr + 8)?;
+
+        Ok(Channel {
+            name_ptr,
+            buffer_ptr,
+            size,
+            write_offset_addr: addr + 12,
+            read_offset_addr: addr + 16,
+        })
+    }
+
+    /// Read the channel's name by following `name_ptr` until a NUL byte. Channels may legally
+    /// have a null name pointer, in which case we report an empty name rather than erroring.
+    fn name(&self, memory: &mut Memory) -> Result<String, Error> {
+        if self.name_ptr == 0 {
+            return Ok(String::new());
+        }
+
+        let mut name = Vec::new();
+        let mut addr = self.name_ptr;
+        loop {
+            let mut byte = [0u8; 1];
+            memory.read_block8(addr, &mut byte)?;
+            if byte[0] == 0 || name.len() > 256 {
+                break;
+            }
+            name.push(byte[0]);
+            addr += 1;
+        }
+
+        Ok(String::from_utf8_lossy(&name).into_owned())
+    }
+}
+
+/// A target-to-host channel.
+pub struct UpChannel(Channel);
+
+impl UpChannel {
+    /// The channel's name, as declared by the target firmware.
+    pub fn name(&self, memory: &mut Memory) -> Result<String, Error> {
+        self.0.name(memory)
+    }
+
+    /// Read as many bytes as are currently available into `buf`, returning the number read.
+    pub fn read(&mut self, memory: &mut Memory, buf: &mut [u8]) -> Result<usize, Error> {
+        let write_off = memory.read_word_32(self.0.write_offset_addr)?;
+        let read_off = memory.read_word_32(self.0.read_offset_addr)?;
+        let size = self.0.size;
+
+        if size == 0 {
+            return Ok(0);
+        }
+
+        let available = (write_off as i64 - read_off as i64).rem_euclid(size as i64) as u32;
+        let count = available.min(buf.len() as u32) as usize;
+
+        let mut read_off = read_off;
+        let mut written = 0;
+        while written < count {
+            let until_wrap = (size - read_off) as usize;
+            let chunk = (count - written).min(until_wrap);
+            memory.read_block8(self.0.buffer_ptr + read_off, &mut buf[written..written + chunk])?;
+            written += chunk;
+            read_off = (read_off + chunk as u32) % size;
+        }
+
+        memory.write_word_32(self.0.read_offset_addr, read_off)?;
+
+        Ok(count)
+    }
+}
+
+/// A host-to-target channel.
+pub struct DownChannel(Channel);
+
+impl DownChannel {
+    /// The channel's name, as declared by the target firmware.
+    pub fn name(&self, memory: &mut Memory) -> Result<String, Error> {
+        self.0.name(memory)
+    }
+
+    /// Write as many bytes of `buf` as there is free space for, returning the number written.
+    pub fn write(&mut self, memory: &mut Memory, buf: &[u8]) -> Result<usize, Error> {
+        let write_off = memory.read_word_32(self.0.write_offset_addr)?;
+        let read_off = memory.read_word_32(self.0.read_offset_addr)?;
+        let size = self.0.size;
+
+        if size == 0 {
+            return Ok(0);
+        }
+
+        // One slot is always left empty so `WrOff == RdOff` is unambiguously "empty".
+        let free = (read_off as i64 - write_off as i64 - 1).rem_euclid(size as i64) as u32;
+        let count = free.min(buf.len() as u32) as usize;
+
+        let mut write_off = write_off;
+        let mut sent = 0;
+        while sent < count {
+            let until_wrap = (size - write_off) as usize;
+            let chunk = (count - sent).min(until_wrap);
+            memory.write_block8(self.0.buffer_ptr + write_off, &buf[sent..sent + chunk])?;
+            sent += chunk;
+            write_off = (write_off + chunk as u32) % size;
+        }
+
+        memory.write_word_32(self.0.write_offset_addr, write_off)?;
+
+        Ok(count)
+    }
+}
+
+/// Search `scan` word-by-word for the RTT magic, returning the control block address.
+///
+/// The target may not have initialized the control block yet (the magic is written last by
+/// firmware startup code), so a failed scan is reported as `Error::Rtt(RttError::ControlBlockNotFound)`
+/// rather than a hard error, and callers are expected to retry.
+fn find_control_block(
+    memory: &mut Memory,
+    scan: &ScanRegion,
+    memory_map: &[MemoryRegion],
+) -> Result<u32, Error> {
+    match scan {
+        ScanRegion::Exact(addr) => {
+            if check_magic(memory, *addr)? {
+                Ok(*addr)
+            } else {
+                Err(Error::Rtt(RttError::ControlBlockNotFound))
+            }
+        }
+        ScanRegion::Range(range) => scan_range(memory, range.start, range.end),
+        ScanRegion::Ram => {
+            for region in memory_map {
+                if let MemoryRegion::Ram(ram) = region {
+                    if let Ok(addr) = scan_range(memory, ram.range.start as u32, ram.range.end as u32) {
+                        return Ok(addr);
+                    }
+                }
+            }
+            Err(Error::Rtt(RttError::ControlBlockNotFound))
+        }
+    }
+}
+
+fn scan_range(memory: &mut Memory, start: u32, end: u32) -> Result<u32, Error> {
+    let mut addr = start;
+    while addr.saturating_add(RTT_ID.len() as u32) <= end {
+        if check_magic(memory, addr)? {
+            return Ok(addr);
+        }
+        addr += 4;
+    }
+    Err(Error::Rtt(RttError::ControlBlockNotFound))
+}
+
+fn check_magic(memory: &mut Memory, addr: u32) -> Result<bool, Error> {
+    let mut buf = [0u8; 16];
+    memory.read_block8(addr, &mut buf)?;
+    Ok(&buf == RTT_ID)
+}
+
+/// Errors specific to locating or decoding an RTT control block.
+#[derive(Debug, thiserror::Error)]
+pub enum RttError {
+    #[error("RTT control block not found in the scanned region")]
+    ControlBlockNotFound,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryInterface;
+
+    /// A synthetic in-memory stand-in for target RAM, driven through the same
+    /// `MemoryInterface` that real probes implement (mirroring `RiscvMemoryInterface`).
+    struct MockMemory {
+        data: Vec<u8>,
+    }
+
+    impl MockMemory {
+        fn new(size: usize) -> Self {
+            MockMemory { data: vec![0; size] }
+        }
+
+        /// Lay out a control block at `addr` with one up and one down channel, matching the
+        /// real SEGGER RTT layout byte-for-byte.
+        fn write_control_block(&mut self, addr: u32, up_buf: u32, up_size: u32, down_buf: u32, down_size: u32) {
+            let addr = addr as usize;
+            self.data[addr..addr + 16].copy_from_slice(RTT_ID);
+            self.data[addr + 16..addr + 20].copy_from_slice(&1u32.to_le_bytes());
+            self.data[addr + 20..addr + 24].copy_from_slice(&1u32.to_le_bytes());
+
+            let up_desc = addr + 24;
+            self.data[up_desc..up_desc + 4].copy_from_slice(&0u32.to_le_bytes());
+            self.data[up_desc + 4..up_desc + 8].copy_from_slice(&up_buf.to_le_bytes());
+            self.data[up_desc + 8..up_desc + 12].copy_from_slice(&up_size.to_le_bytes());
+
+            let down_desc = up_desc + 24;
+            self.data[down_desc..down_desc + 4].copy_from_slice(&0u32.to_le_bytes());
+            self.data[down_desc + 4..down_desc + 8].copy_from_slice(&down_buf.to_le_bytes());
+            self.data[down_desc + 8..down_desc + 12].copy_from_slice(&down_size.to_le_bytes());
+        }
+    }
+
+    impl MemoryInterface for MockMemory {
+        fn read_word_32(&mut self, address: u32) -> Result<u32, Error> {
+            let mut buf = [0u8; 4];
+            self.read_block8(address, &mut buf)?;
+            Ok(u32::from_le_bytes(buf))
+        }
+
+        fn read_word_8(&mut self, address: u32) -> Result<u8, Error> {
+            Ok(self.data[address as usize])
+        }
+
+        fn read_block32(&mut self, address: u32, data: &mut [u32]) -> Result<(), Error> {
+            for (i, word) in data.iter_mut().enumerate() {
+                *word = self.read_word_32(address + (i as u32) * 4)?;
+            }
+            Ok(())
+        }
+
+        fn read_block8(&mut self, address: u32, data: &mut [u8]) -> Result<(), Error> {
+            let start = address as usize;
+            data.copy_from_slice(&self.data[start..start + data.len()]);
+            Ok(())
+        }
+
+        fn write_word_32(&mut self, address: u32, data: u32) -> Result<(), Error> {
+            self.write_block8(address, &data.to_le_bytes())
+        }
+
+        fn write_word_8(&mut self, address: u32, data: u8) -> Result<(), Error> {
+            self.data[address as usize] = data;
+            Ok(())
+        }
+
+        fn write_block32(&mut self, address: u32, data: &[u32]) -> Result<(), Error> {
+            for (i, word) in data.iter().enumerate() {
+                self.write_word_32(address + (i as u32) * 4, *word)?;
+            }
+            Ok(())
+        }
+
+        fn write_block8(&mut self, address: u32, data: &[u8]) -> Result<(), Error> {
+            let start = address as usize;
+            self.data[start..start + data.len()].copy_from_slice(data);
+            Ok(())
+        }
+    }
+
+    /// Build a `Memory` around a `MockMemory` with one up (128, size 16) and one down
+    /// (192, size 16) channel, ready to be handed to `Rtt::attach`.
+    fn mock_session() -> Memory {
+        let mut mock = MockMemory::new(256);
+        mock.write_control_block(0, 128, 16, 192, 16);
+        Memory::new(mock)
+    }
+
+    #[test]
+    fn attach_finds_control_block_and_channels() {
+        let memory = mock_session();
+        let mut rtt = Rtt::attach(memory, ScanRegion::Exact(0), &[]).expect("control block not found");
+
+        assert_eq!(rtt.ptr(), 0);
+        assert_eq!(rtt.up_channels().len(), 1);
+        assert_eq!(rtt.down_channels().len(), 1);
+    }
+
+    #[test]
+    fn attach_fails_before_firmware_writes_the_magic() {
+        // An all-zero region has no "SEGGER RTT" magic yet, as if firmware hasn't initialized
+        // RTT - this must be reported as a (retryable) not-found, not a panic or hard error.
+        let memory = Memory::new(MockMemory::new(256));
+        let err = Rtt::attach(memory, ScanRegion::Exact(0), &[]).unwrap_err();
+        assert!(matches!(err, Error::Rtt(RttError::ControlBlockNotFound)));
+    }
+
+    #[test]
+    fn up_channel_read_handles_wraparound() {
+        let memory = mock_session();
+        let mut rtt = Rtt::attach(memory, ScanRegion::Exact(0), &[]).unwrap();
+
+        // Up channel buffer lives at 128, size 16. Seed it so the unread region wraps: RdOff =
+        // 12, WrOff = 4 -> 8 bytes available, spanning the end of the buffer.
+        rtt.memory().write_block8(128 + 12, &[1, 2, 3, 4]).unwrap();
+        rtt.memory().write_block8(128, &[5, 6, 7, 8]).unwrap();
+        // Up channel descriptor starts right after the header (offset 24).
+        rtt.memory().write_word_32(24 + 12, 4).unwrap(); // WrOff
+        rtt.memory().write_word_32(24 + 16, 12).unwrap(); // RdOff
+
+        let Rtt { memory, up_channels, .. } = &mut rtt;
+        let mut buf = [0u8; 8];
+        let count = up_channels[0].read(memory, &mut buf).unwrap();
+
+        assert_eq!(count, 8);
+        assert_eq!(buf, [1, 2, 3, 4, 5, 6, 7, 8]);
+        // RdOff should have advanced to meet WrOff.
+        assert_eq!(rtt.memory().read_word_32(24 + 16).unwrap(), 4);
+    }
+
+    #[test]
+    fn down_channel_write_respects_one_slot_free_and_wraps() {
+        let memory = mock_session();
+        let mut rtt = Rtt::attach(memory, ScanRegion::Exact(0), &[]).unwrap();
+
+        // Down channel descriptor is the second one, at offset 24 + 24 = 48. Buffer at 192,
+        // size 16. RdOff = WrOff = 14 -> the buffer is empty, so 15 bytes are free (16 minus
+        // the one slot always left empty). A 4-byte write fits whole, and since only two bytes
+        // remain before the end of the buffer it wraps around to the start.
+        rtt.memory().write_word_32(48 + 12, 14).unwrap(); // WrOff
+        rtt.memory().write_word_32(48 + 16, 14).unwrap(); // RdOff
+
+        let Rtt { memory, down_channels, .. } = &mut rtt;
+        let count = down_channels[0].write(memory, &[0xaa, 0xbb, 0xcc, 0xdd]).unwrap();
+
+        assert_eq!(count, 4);
+        // First two bytes land at the end of the buffer (offsets 14, 15), the rest wrap to the
+        // start (offsets 0, 1).
+        let mut written = [0u8; 4];
+        rtt.memory().read_block8(192 + 14, &mut written[0..2]).unwrap();
+        rtt.memory().read_block8(192, &mut written[2..4]).unwrap();
+        assert_eq!(written, [0xaa, 0xbb, 0xcc, 0xdd]);
+        // WrOff should have advanced and wrapped: 14 + 4 = 18, mod 16 = 2.
+        assert_eq!(rtt.memory().read_word_32(48 + 12).unwrap(), 2);
+    }
+
+    #[test]
+    fn down_channel_write_is_truncated_when_almost_full() {
+        let memory = mock_session();
+        let mut rtt = Rtt::attach(memory, ScanRegion::Exact(0), &[]).unwrap();
+
+        // Down channel buffer size 16; RdOff = WrOff + 1 means there is zero free space (one
+        // slot is always kept empty), so nothing should be written.
+        rtt.memory().write_word_32(48 + 12, 0).unwrap(); // WrOff
+        rtt.memory().write_word_32(48 + 16, 1).unwrap(); // RdOff
+
+        let Rtt { memory, down_channels, .. } = &mut rtt;
+        let count = down_channels[0].write(memory, &[1, 2, 3]).unwrap();
+        assert_eq!(count, 0);
+    }
+}
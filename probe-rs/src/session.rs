@@ -1,13 +1,19 @@
 use crate::architecture::arm::{
     ap::{MemoryAP},
+    chip_info::ArmChipInfo,
     memory::romtable::{RomTable},
     memory::ADIMemoryInterface,
+    swo::{Decoder as SwoDecoder, TracePacket},
     ArmCommunicationInterface,
 };
+use crate::architecture::riscv::RiscvCommunicationInterface;
 use crate::config::{
-    MemoryRegion, RawFlashAlgorithm, RegistryError, Target, TargetSelector,
+    registry::ChipInfo, Architecture, MemoryRegion, RawFlashAlgorithm, RegistryError, Target,
+    TargetSelector,
 };
 use crate::core::CoreType;
+use crate::debug::{self, ElfFile, Frame};
+use crate::rtt::{Rtt, ScanRegion};
 use crate::{Core, CoreList, Error, Memory, MemoryList, Probe};
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -20,57 +26,75 @@ pub struct Session {
 struct InnerSession {
     target: Target,
     architecture_session: ArchitectureSession,
+    swo_decoder: SwoDecoder,
 }
 
 enum ArchitectureSession {
     Arm(ArmCommunicationInterface),
+    Riscv(RiscvCommunicationInterface),
 }
 
 impl Session {
     /// Open a new session with a given debug target
     pub fn new(probe: Probe, target: impl Into<TargetSelector>) -> Result<Self, Error> {
-        // TODO: Handle different architectures
-
-        let arm_interface = ArmCommunicationInterface::new(probe);
-
         let target = target.into();
-        let target = match target.into() {
+        let (target, architecture_session) = match target.into() {
             TargetSelector::Unspecified(name) => {
-                match crate::config::registry::get_target_by_name(name) {
+                let target = match crate::config::registry::get_target_by_name(name) {
                     Ok(target) => target,
                     Err(err) => return Err(err)?,
-                }
+                };
+                let session = Self::open_architecture_session(target.architecture, probe)?;
+                (target, session)
+            }
+            TargetSelector::Specified(target) => {
+                let session = Self::open_architecture_session(target.architecture, probe)?;
+                (target, session)
             }
-            TargetSelector::Specified(target) => target,
             TargetSelector::Auto => {
-                let arm_chip = None;
-                // TODO: Replace this with a generic core!
-                // let arm_chip = ArmChipInfo::read_from_rom_table(core, &mut arm_interface)
-                //     .map(|option| option.map(ChipInfo::Arm))?;
-                if let Some(chip) = arm_chip {
-                    match crate::config::registry::get_target_by_chip_info(chip) {
+                // Chip auto-detection currently only walks the ARM debug ROM table, so the
+                // interface it builds to do that doubles as the session's final one.
+                let mut arm_interface = ArmCommunicationInterface::new(probe);
+                let arm_chip = ArmChipInfo::read_from_rom_table(&mut arm_interface)?;
+                let target = if let Some(chip) = arm_chip {
+                    match crate::config::registry::get_target_by_chip_info(ChipInfo::from(chip)) {
                         Ok(target) => target,
                         Err(err) => return Err(err)?,
                     }
                 } else {
-                    // Not sure if this is ok.
                     return Err(Error::ChipNotFound(RegistryError::ChipAutodetectFailed));
-                }
+                };
+                (target, ArchitectureSession::Arm(arm_interface))
             }
         };
 
-        let session = ArchitectureSession::Arm(arm_interface);
-
         Ok(Self {
             inner: Rc::new(RefCell::new(InnerSession {
                 target,
-                architecture_session: session,
+                architecture_session,
+                swo_decoder: SwoDecoder::new(),
             })),
         })
     }
 
+    /// Build the communication interface for `architecture`, consuming `probe`.
+    fn open_architecture_session(architecture: Architecture, probe: Probe) -> Result<ArchitectureSession, Error> {
+        match architecture {
+            Architecture::Arm => Ok(ArchitectureSession::Arm(ArmCommunicationInterface::new(probe))),
+            Architecture::Riscv => Ok(ArchitectureSession::Riscv(RiscvCommunicationInterface::new(probe)?)),
+        }
+    }
+
     pub fn list_cores(&self) -> CoreList {
-        CoreList::new(vec![self.inner.borrow().target.core_type.clone()])
+        CoreList::new(
+            self.inner
+                .borrow()
+                .target
+                .cores
+                .iter()
+                .map(|core| core.core_type.clone())
+                .collect(),
+        )
     }
 
     pub fn attach_to_core(&self, n: usize) -> Result<Core, Error> {
@@ -80,9 +104,8 @@ impl Session {
             .ok_or_else(|| Error::CoreNotFound(n))?
             .attach(self.clone(), self.attach_to_memory(n)?, n);
         match self.inner.borrow_mut().architecture_session {
-            ArchitectureSession::Arm(ref mut _interface) => {
-                Ok(core)
-            }
+            ArchitectureSession::Arm(ref mut _interface) => Ok(core),
+            ArchitectureSession::Riscv(ref mut _interface) => Ok(core),
         }
     }
 
@@ -104,7 +127,7 @@ impl Session {
                 self.clone(),
                 match memory {
                     Some(memory) => memory,
-                    None => self.attach_to_memory(0)?,
+                    None => self.attach_to_memory(n)?,
                 },
                 n,
             );
@@ -112,42 +135,61 @@ impl Session {
     }
 
     pub fn list_memories(&self) -> MemoryList {
-        MemoryList::new(vec![])
+        MemoryList::new(self.inner.borrow().target.cores.iter().map(|core| core.ap).collect())
     }
 
+    /// Attach to the memory view of core `id`. On ARM this goes through the memory AP that the
+    /// core's `CoreAccessOptions::ap` selects, unless the probe has a dedicated memory interface,
+    /// which is then returned for every core. On RISC-V every core gets the same Debug Module
+    /// memory view and `ap` is not used.
     pub fn attach_to_memory(&self, id: usize) -> Result<Memory, Error> {
+        let ap = self
+            .inner
+            .borrow()
+            .target
+            .cores
+            .get(id)
+            .ok_or_else(|| Error::CoreNotFound(id))?
+            .ap;
+
         match self.inner.borrow_mut().architecture_session {
             ArchitectureSession::Arm(ref mut interface) => {
                 if let Some(memory) = interface.dedicated_memory_interface() {
                     Ok(memory)
                 } else {
-                    // TODO: Change this to actually grab the proper memory IF.
-                    // For now always use the ARM IF.
                     let maps = interface.memory_access_ports()?;
                     Ok(Memory::new(
                         ADIMemoryInterface::<ArmCommunicationInterface>::new(
                             interface.clone(),
-                            maps[id].id(),
+                            maps[ap].id(),
                         ),
                     ))
                 }
             }
+            ArchitectureSession::Riscv(ref interface) => Ok(interface.memory_interface()),
         }
     }
 
+    /// Attach to whichever memory view is "best" when the caller doesn't care which core it
+    /// belongs to - in practice, core 0's.
     pub fn attach_to_best_memory(&self) -> Result<Memory, Error> {
-        match self.inner.borrow().architecture_session {
-            ArchitectureSession::Arm(ref interface) => {
+        let ap = self.inner.borrow().target.cores.get(0).map_or(0, |core| core.ap);
+
+        match self.inner.borrow_mut().architecture_session {
+            ArchitectureSession::Arm(ref mut interface) => {
                 if let Some(memory) = interface.dedicated_memory_interface() {
                     Ok(memory)
                 } else {
-                    // TODO: Change this to actually grab the proper memory IF.
-                    // For now always use the ARM IF.
+                    let maps = interface.memory_access_ports()?;
                     Ok(Memory::new(
-                        ADIMemoryInterface::<ArmCommunicationInterface>::new(interface.clone(), 0),
+                        ADIMemoryInterface::<ArmCommunicationInterface>::new(
+                            interface.clone(),
+                            maps[ap].id(),
+                        ),
                     ))
                 }
             }
+            ArchitectureSession::Riscv(ref interface) => Ok(interface.memory_interface()),
         }
     }
 
@@ -162,9 +204,37 @@ impl Session {
     pub fn read_swv(&self) -> Result<Vec<u8>, Error> {
         match &mut self.inner.borrow_mut().architecture_session {
             ArchitectureSession::Arm(interface) => interface.read_swv(),
+            ArchitectureSession::Riscv(_) => {
+                Err(crate::architecture::riscv::unsupported("SWV/SWO tracing"))
+            }
         }
     }
 
+    /// Read and decode a chunk of raw SWV/SWO bytes into structured ITM/DWT trace packets.
+    ///
+    /// Packets that straddle two calls are buffered internally, so callers can just poll this
+    /// repeatedly rather than managing their own residual-byte buffer.
+    pub fn read_trace_packets(&self) -> Result<Vec<TracePacket>, Error> {
+        let raw = self.read_swv()?;
+        Ok(self.inner.borrow_mut().swo_decoder.feed(&raw))
+    }
+
+    /// Attach to the target's RTT control block, scanning `scan` for the `SEGGER RTT` magic.
+    ///
+    /// The control block is only written by firmware once RTT has been initialized, so this
+    /// can fail with `Error::Rtt` even against a target that will support RTT a few
+    /// instructions later; callers that attach right after reset may need to retry.
+    pub fn attach_rtt(&self, scan: ScanRegion) -> Result<Rtt, Error> {
+        let memory = self.attach_to_best_memory()?;
+        let memory_map = self.memory_map();
+        Rtt::attach(memory, scan, &memory_map)
+    }
+
+    /// Produce a symbolized call stack for a halted core, using `.debug_frame` CFI from `elf`.
+    pub fn unwind(&self, core: &mut Core, elf: &ElfFile) -> Result<Vec<Frame>, Error> {
+        debug::unwind(core, elf)
+    }
+
     pub fn setup_tracing(&mut self, core: &mut Core) -> Result<(), Error> {
         match self.inner.borrow_mut().architecture_session {
             ArchitectureSession::Arm(ref mut interface) => {
@@ -187,6 +257,9 @@ impl Session {
 
                 crate::architecture::arm::component::setup_tracing(core, &rom_table)
             }
+            ArchitectureSession::Riscv(_) => {
+                Err(crate::architecture::riscv::unsupported("ITM/DWT tracing setup"))
+            }
         }
     }
 